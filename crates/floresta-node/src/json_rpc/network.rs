@@ -1,9 +1,12 @@
 //! This module holds all RPC server side methods for interacting with our node's network stack.
-
-use std::net::IpAddr;
-use std::net::SocketAddr;
-
-use bitcoin::Network;
+//!
+//! `self.node` is expected to implement [`floresta_wire::node_interface::NodeInterface`]; see
+//! that trait for the full contract these handlers rely on.
+
+use floresta_wire::address_manager::KnownAddress;
+use floresta_wire::banman::BanEntry;
+use floresta_wire::banman::Subnet;
+use floresta_wire::node_interface::PeerAddress;
 use floresta_wire::node_interface::PeerInfo;
 use serde_json::json;
 use serde_json::Value;
@@ -28,27 +31,11 @@ impl<Blockchain: RpcChain> RpcImpl<Blockchain> {
         command: String,
         v2transport: bool,
     ) -> Result<Value> {
-        // Try to parse both IP address and port.
-        let (addr, port) = if let Ok(socket_addr) = node_address.parse::<SocketAddr>() {
-            (socket_addr.ip(), socket_addr.port())
-        // Try to parse the IP address only, and append the default P2P port for the network.
-        } else {
-            let ip = node_address
-                .parse::<IpAddr>()
-                .map_err(|_| JsonRpcError::InvalidAddress)?;
-
-            // TODO: use `NetworkExt` to append the correct port once
-            // https://github.com/rust-bitcoin/rust-bitcoin/pull/4639 makes it into a release.
-            let default_port = match self.network {
-                Network::Bitcoin => 8333,
-                Network::Signet => 38333,
-                Network::Testnet => 18333,
-                Network::Testnet4 => 48333,
-                Network::Regtest => 18444,
-            };
-
-            (ip, default_port)
-        };
+        // Accepts a clearnet IP (with or without a port), a `.onion` (Tor v3) address or a
+        // `.b32.i2p` (I2P) address, appending the network's conventional P2P port when one
+        // isn't given.
+        let (addr, port) = PeerAddress::parse(&node_address, self.network)
+            .map_err(|_| JsonRpcError::InvalidAddress)?;
 
         let _ = match command.as_str() {
             "add" => self.node.add_peer(addr, port, v2transport).await,
@@ -66,16 +53,9 @@ impl<Blockchain: RpcChain> RpcImpl<Blockchain> {
         node_id: Option<u32>,
     ) -> Result<Value> {
         let (peer_addr, peer_port) = match (node_address.is_empty(), node_id) {
-            // Reference the peer by it's IP address and port.
-            (false, None) => {
-                // Try to parse `node_address` into a `SocketAddr`.
-                // This will handle IPv4:port and IPv6:port.
-                let socket_addr = node_address
-                    .parse::<SocketAddr>()
-                    .map_err(|_| JsonRpcError::InvalidAddress)?;
-
-                (socket_addr.ip(), socket_addr.port())
-            }
+            // Reference the peer by it's address and port.
+            (false, None) => PeerAddress::parse(&node_address, self.network)
+                .map_err(|_| JsonRpcError::InvalidAddress)?,
             // Reference the peer by it's ID.
             (true, Some(node_id)) => {
                 let peer_info = self
@@ -89,7 +69,7 @@ impl<Blockchain: RpcChain> RpcImpl<Blockchain> {
                     .find(|peer| peer.id == node_id)
                     .ok_or(JsonRpcError::PeerNotFound)?;
 
-                (peer.address.ip(), peer.address.port())
+                (peer.address.clone(), peer.port)
             }
             // Both address and ID were provided, or neither was provided.
             _ => {
@@ -116,4 +96,80 @@ impl<Blockchain: RpcChain> RpcImpl<Blockchain> {
             .await
             .map_err(|_| JsonRpcError::Node("Failed to get peer information".to_string()))
     }
+
+    pub(crate) async fn set_ban(
+        &self,
+        subnet: String,
+        command: String,
+        bantime: Option<u64>,
+        absolute: bool,
+    ) -> Result<Value> {
+        let subnet: Subnet = subnet.parse().map_err(|_| JsonRpcError::InvalidAddress)?;
+
+        match command.as_str() {
+            "add" => {
+                self.node
+                    .ban(subnet, bantime, absolute)
+                    .await
+                    .map_err(|e| JsonRpcError::Node(e.to_string()))?;
+            }
+            "remove" => {
+                let removed = self
+                    .node
+                    .unban(subnet)
+                    .await
+                    .map_err(|e| JsonRpcError::Node(e.to_string()))?;
+
+                if !removed {
+                    return Err(JsonRpcError::PeerNotFound);
+                }
+            }
+            _ => return Err(JsonRpcError::InvalidAddnodeCommand),
+        }
+
+        Ok(json!(null))
+    }
+
+    pub(crate) async fn list_banned(&self) -> Result<Vec<BanEntry>> {
+        self.node
+            .list_banned()
+            .await
+            .map_err(|e| JsonRpcError::Node(e.to_string()))
+    }
+
+    pub(crate) async fn clear_banned(&self) -> Result<Value> {
+        self.node
+            .clear_banned()
+            .await
+            .map_err(|e| JsonRpcError::Node(e.to_string()))?;
+
+        Ok(json!(null))
+    }
+
+    /// Returns known good peer addresses from the address manager, optionally restricted to
+    /// a given network (`"ipv4"`, `"ipv6"`, `"torv3"`, `"i2p"` or `"cjdns"`).
+    pub(crate) async fn get_node_addresses(
+        &self,
+        count: Option<usize>,
+        network: Option<String>,
+    ) -> Result<Vec<KnownAddress>> {
+        self.node
+            .get_node_addresses(count.unwrap_or(1), network.as_deref())
+            .await
+            .map_err(|e| JsonRpcError::Node(e.to_string()))
+    }
+
+    /// Adds `address` to the set of externally reachable addresses this node advertises to
+    /// its peers, in place of relying on them to guess it.
+    pub(crate) async fn set_external_addr(&self, address: String) -> Result<Value> {
+        let (addr, port) =
+            PeerAddress::parse(&address, self.network).map_err(|_| JsonRpcError::InvalidAddress)?;
+
+        self.node
+            .add_external_addr(addr, port)
+            .await
+            .map_err(|e| JsonRpcError::Node(e.to_string()))?;
+
+        Ok(json!(null))
+    }
 }
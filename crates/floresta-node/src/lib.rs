@@ -12,7 +12,10 @@ mod error;
 mod florestad;
 #[cfg(feature = "json-rpc")]
 mod json_rpc;
+#[cfg(feature = "rest")]
+mod rest;
 mod slip132;
+pub mod tor_control;
 mod wallet_input;
 #[cfg(feature = "zmq-server")]
 mod zmq;
@@ -21,3 +24,5 @@ pub use florestad::AssumeUtreexoValue;
 pub use florestad::AssumeValidArg;
 pub use florestad::Config;
 pub use florestad::Florestad;
+pub use tor_control::OnionService;
+pub use tor_control::TorController;
@@ -0,0 +1,191 @@
+//! A stateless HTTP REST interface for tools that prefer plain GETs over JSON-RPC.
+//!
+//! Gated behind the `rest` feature, this serves read-only endpoints mirroring Bitcoin Core's
+//! `/rest` interface, plus a Utreexo-specific endpoint for inclusion proofs:
+//!
+//! - `GET /rest/block/<hash>.{bin,hex,json}`
+//! - `GET /rest/tx/<txid>.{bin,hex,json}`
+//! - `GET /rest/headers/<count>/<hash>.{bin,hex}`
+//! - `GET /rest/utreexo/proof/<txid>.json`
+//!
+//! The binary/hex/json choice is made by the file-extension suffix, same as Bitcoin Core's
+//! REST interface. This server shares the same [`RpcChain`] handle `RpcImpl` already holds,
+//! so both interfaces see the same view of the chain.
+
+use std::io::Cursor;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::consensus::Encodable;
+use bitcoin::BlockHash;
+use bitcoin::Txid;
+use tiny_http::Response;
+use tiny_http::Server;
+
+use crate::json_rpc::server::RpcChain;
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+/// The content negotiation formats the REST interface supports, selected by the
+/// file-extension suffix of the request path (`.bin`, `.hex` or `.json`).
+enum Format {
+    Binary,
+    Hex,
+    Json,
+}
+
+impl Format {
+    /// Strips a known format suffix off `path`, returning the remaining stem.
+    fn split(path: &str) -> Option<(&str, Self)> {
+        path.strip_suffix(".bin")
+            .map(|stem| (stem, Format::Binary))
+            .or_else(|| path.strip_suffix(".hex").map(|stem| (stem, Format::Hex)))
+            .or_else(|| path.strip_suffix(".json").map(|stem| (stem, Format::Json)))
+    }
+}
+
+/// Serves the `/rest/*` endpoints over plain HTTP, reusing the same chain handle as the
+/// JSON-RPC server.
+pub struct RestServer<Blockchain: RpcChain> {
+    chain: Arc<Blockchain>,
+}
+
+impl<Blockchain: RpcChain> RestServer<Blockchain> {
+    /// Creates a REST server sharing `chain` with the JSON-RPC server.
+    pub fn new(chain: Arc<Blockchain>) -> Self {
+        Self { chain }
+    }
+
+    /// Binds to `addr` and serves requests until the process exits. Like the `zmq-server`
+    /// feature, this is meant to be spawned on its own task by `florestad`.
+    pub fn serve(self, addr: &str) -> std::io::Result<()> {
+        let server = Server::http(addr).map_err(std::io::Error::other)?;
+
+        for request in server.incoming_requests() {
+            let response = self.handle(request.url());
+            let _ = request.respond(response);
+        }
+
+        Ok(())
+    }
+
+    fn handle(&self, url: &str) -> HttpResponse {
+        let Some(path) = url.strip_prefix("/rest/") else {
+            return not_found();
+        };
+
+        if let Some(rest) = path.strip_prefix("block/") {
+            return self.block(rest);
+        }
+        if let Some(rest) = path.strip_prefix("tx/") {
+            return self.tx(rest);
+        }
+        if let Some(rest) = path.strip_prefix("headers/") {
+            return self.headers(rest);
+        }
+        if let Some(rest) = path.strip_prefix("utreexo/proof/") {
+            return self.utreexo_proof(rest);
+        }
+
+        not_found()
+    }
+
+    fn block(&self, path: &str) -> HttpResponse {
+        let Some((hash, format)) = Format::split(path) else {
+            return not_found();
+        };
+        let Ok(hash) = BlockHash::from_str(hash) else {
+            return not_found();
+        };
+        let Ok(Some(block)) = self.chain.get_block(&hash) else {
+            return not_found();
+        };
+
+        encode(&block, format)
+    }
+
+    fn tx(&self, path: &str) -> HttpResponse {
+        let Some((txid, format)) = Format::split(path) else {
+            return not_found();
+        };
+        let Ok(txid) = Txid::from_str(txid) else {
+            return not_found();
+        };
+        let Ok(Some(tx)) = self.chain.get_transaction(&txid) else {
+            return not_found();
+        };
+
+        encode(&tx, format)
+    }
+
+    fn headers(&self, path: &str) -> HttpResponse {
+        let Some((path, format)) = Format::split(path) else {
+            return not_found();
+        };
+        let Some((count, hash)) = path.split_once('/') else {
+            return not_found();
+        };
+        let (Ok(count), Ok(hash)) = (count.parse::<usize>(), BlockHash::from_str(hash)) else {
+            return not_found();
+        };
+        let Ok(headers) = self.chain.get_headers(&hash, count) else {
+            return not_found();
+        };
+
+        match format {
+            Format::Binary => {
+                let mut buf = Vec::new();
+                for header in &headers {
+                    let _ = header.consensus_encode(&mut buf);
+                }
+                Response::from_data(buf)
+            }
+            Format::Hex => {
+                let hex = headers.iter().map(serialize_hex).collect::<Vec<_>>().join("");
+                Response::from_string(hex)
+            }
+            // Bitcoin Core's `/rest/headers` endpoint doesn't support JSON either; headers
+            // are cheap enough to fetch as hex and decode client-side.
+            Format::Json => return not_found(),
+        }
+    }
+
+    /// Returns the Utreexo inclusion proof for `txid`'s outputs against the current
+    /// accumulator roots, as JSON.
+    fn utreexo_proof(&self, path: &str) -> HttpResponse {
+        let Some((txid, Format::Json)) = Format::split(path) else {
+            return not_found();
+        };
+        let Ok(txid) = Txid::from_str(txid) else {
+            return not_found();
+        };
+        let Ok(Some(proof)) = self.chain.get_utreexo_proof(&txid) else {
+            return not_found();
+        };
+
+        match serde_json::to_string(&proof) {
+            Ok(json) => Response::from_string(json),
+            Err(_) => not_found(),
+        }
+    }
+}
+
+fn encode<T: Encodable + serde::Serialize>(value: &T, format: Format) -> HttpResponse {
+    match format {
+        Format::Binary => {
+            let mut buf = Vec::new();
+            let _ = value.consensus_encode(&mut buf);
+            Response::from_data(buf)
+        }
+        Format::Hex => Response::from_string(serialize_hex(value)),
+        Format::Json => match serde_json::to_string(value) {
+            Ok(json) => Response::from_string(json),
+            Err(_) => not_found(),
+        },
+    }
+}
+
+fn not_found() -> HttpResponse {
+    Response::from_string("not found").with_status_code(404)
+}
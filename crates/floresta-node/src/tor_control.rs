@@ -0,0 +1,145 @@
+//! A minimal client for Tor's control port protocol, used to stand up an ephemeral v3
+//! hidden service so `florestad` can advertise an onion address to its peers without the
+//! operator having to edit `torrc` by hand.
+
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufStream;
+use tokio::net::TcpStream;
+
+/// An ephemeral onion service created through the control port. It's torn down (and the
+/// address stops being reachable) once the control connection is closed.
+#[derive(Debug)]
+pub struct OnionService {
+    /// The service's address, without the `.onion` suffix.
+    pub service_id: String,
+    /// The virtual port peers should connect to; maps to our local P2P listener.
+    pub port: u16,
+}
+
+impl OnionService {
+    /// The full onion address peers should dial, e.g. `<service_id>.onion`.
+    pub fn address(&self) -> String {
+        format!("{}.onion", self.service_id)
+    }
+}
+
+/// A connection to Tor's control port (commonly `127.0.0.1:9051`).
+pub struct TorController {
+    stream: BufStream<TcpStream>,
+}
+
+impl TorController {
+    /// Connects to the control port and authenticates using `cookie`-less authentication
+    /// (`AUTHENTICATE` with no argument), which Tor accepts when `CookieAuthentication` is
+    /// disabled and no control password is set.
+    pub async fn connect(control_addr: &str) -> std::io::Result<Self> {
+        let stream = TcpStream::connect(control_addr).await?;
+        let mut controller = Self {
+            stream: BufStream::new(stream),
+        };
+        controller.command("AUTHENTICATE").await?;
+        Ok(controller)
+    }
+
+    /// Asks Tor to create a new ephemeral v3 hidden service, forwarding the service's
+    /// virtual `port` to `target_port` on the local host, and returns the resulting address.
+    ///
+    /// The service's private key is discarded (`NEW:BEST`, no `Detach`), so the address
+    /// disappears once this control connection closes. That's the right tradeoff for a
+    /// node that just wants to be reachable while it's running.
+    pub async fn create_onion_service(
+        &mut self,
+        port: u16,
+        target_port: u16,
+    ) -> std::io::Result<OnionService> {
+        let reply = self
+            .command(&format!("ADD_ONION NEW:BEST Port={port},{target_port}"))
+            .await?;
+
+        let service_id = reply
+            .lines()
+            .find_map(|line| line.strip_prefix("250-ServiceID="))
+            .ok_or_else(|| {
+                std::io::Error::other("Tor control port reply didn't contain a ServiceID")
+            })?
+            .trim()
+            .to_string();
+
+        Ok(OnionService { service_id, port })
+    }
+
+    /// Sends a single control-port command and returns the (multi-line) reply, stripped of
+    /// the final `250 OK` status line.
+    async fn command(&mut self, command: &str) -> std::io::Result<String> {
+        self.stream
+            .write_all(format!("{command}\r\n").as_bytes())
+            .await?;
+        self.stream.flush().await?;
+
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            self.stream.read_line(&mut line).await?;
+            if line.is_empty() {
+                return Err(std::io::Error::other(
+                    "Tor control port closed the connection unexpectedly",
+                ));
+            }
+
+            // A reply is terminated by a line of the form `<code> <text>` (as opposed to
+            // `<code>-<text>` for intermediate lines).
+            let done = line.as_bytes().get(3) == Some(&b' ');
+            if !line.starts_with("250") {
+                return Err(std::io::Error::other(format!(
+                    "Tor control port returned an error: {line}"
+                )));
+            }
+
+            if done {
+                return Ok(reply);
+            }
+            reply.push_str(&line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn creates_an_onion_service_through_a_fake_control_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server_task = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufStream::new(stream);
+
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "AUTHENTICATE\r\n");
+            stream.write_all(b"250 OK\r\n").await.unwrap();
+            stream.flush().await.unwrap();
+
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "ADD_ONION NEW:BEST Port=9050,9051\r\n");
+            stream
+                .write_all(b"250-ServiceID=fakeserviceid\r\n250 OK\r\n")
+                .await
+                .unwrap();
+            stream.flush().await.unwrap();
+        });
+
+        let mut controller = TorController::connect(&addr.to_string()).await.unwrap();
+        let service = controller.create_onion_service(9050, 9051).await.unwrap();
+
+        assert_eq!(service.service_id, "fakeserviceid");
+        assert_eq!(service.address(), "fakeserviceid.onion");
+        server_task.await.unwrap();
+    }
+}
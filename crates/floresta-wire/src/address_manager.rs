@@ -0,0 +1,222 @@
+//! Tracks addresses of peers we've seen, so we can answer `getnodeaddresses` and have
+//! something to gossip even before we've connected to anyone, plus the set of addresses we
+//! advertise for ourselves (see [`ExternalAddresses`]).
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use bitcoin::p2p::ServiceFlags;
+
+use crate::node_interface::PeerAddress;
+
+/// How long since an address was last seen before we consider it stale and stop gossiping it,
+/// mirroring Bitcoin Core addrman's `ADDRMAN_HORIZON_DAYS` (30 days).
+const STALE_AFTER_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// A peer address we know about, along with when we last confirmed it was reachable and
+/// which services it claims to offer.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct KnownAddress {
+    /// The address itself.
+    pub address: PeerAddress,
+    /// The port this peer listens on.
+    pub port: u16,
+    /// The service flags this peer advertised, as a raw bitmask.
+    pub services: u64,
+    /// Unix timestamp (seconds) we last saw this address advertised or connected to.
+    pub last_seen: u64,
+}
+
+/// Stores addresses gossiped to us by peers, queryable by network type for
+/// `getnodeaddresses`.
+#[derive(Debug, Default)]
+pub struct AddressManager {
+    addresses: HashMap<(PeerAddress, u16), KnownAddress>,
+}
+
+impl AddressManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records (or refreshes) a peer address we've learned about: the last-seen time is
+    /// pushed forward and the service flags are replaced with the freshly advertised ones,
+    /// since a peer can add or drop services between advertisements.
+    pub fn add(&mut self, address: PeerAddress, port: u16, services: ServiceFlags, seen_at: u64) {
+        self.addresses
+            .entry((address.clone(), port))
+            .and_modify(|known| {
+                known.last_seen = known.last_seen.max(seen_at);
+                known.services = services.to_u64();
+            })
+            .or_insert(KnownAddress {
+                address,
+                port,
+                services: services.to_u64(),
+                last_seen: seen_at,
+            });
+    }
+
+    /// Returns up to `count` known-good addresses, optionally restricted to `network` (as
+    /// reported by [`PeerAddress::network`], e.g. `"ipv4"` or `"torv3"`), most recently seen
+    /// first. "Known good" excludes addresses we haven't seen in [`STALE_AFTER_SECS`], since
+    /// gossiping those just wastes our peers' connection attempts.
+    pub fn get(&self, count: usize, network: Option<&str>) -> Vec<KnownAddress> {
+        let now = now();
+
+        let mut addresses: Vec<&KnownAddress> = self
+            .addresses
+            .values()
+            .filter(|known| match network {
+                Some(net) => known.address.network() == net,
+                None => true,
+            })
+            .filter(|known| now.saturating_sub(known.last_seen) <= STALE_AFTER_SECS)
+            .collect();
+
+        addresses.sort_unstable_by(|a, b| {
+            b.last_seen
+                .cmp(&a.last_seen)
+                .then_with(|| (a.address.clone(), a.port).cmp(&(b.address.clone(), b.port)))
+        });
+
+        addresses.into_iter().take(count).cloned().collect()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use super::*;
+
+    fn addr(last_octet: u8) -> PeerAddress {
+        PeerAddress::Ipv4(Ipv4Addr::new(127, 0, 0, last_octet))
+    }
+
+    #[test]
+    fn re_adding_an_address_refreshes_its_services_instead_of_keeping_the_stale_ones() {
+        // Timestamps are relative to now, so `get` doesn't filter them out as stale.
+        let base = now() - 1000;
+        let mut manager = AddressManager::new();
+        manager.add(addr(1), 8333, ServiceFlags::NETWORK, base + 100);
+        manager.add(addr(1), 8333, ServiceFlags::NETWORK | ServiceFlags::WITNESS, base + 200);
+
+        let known = manager.get(1, None);
+        assert_eq!(known.len(), 1);
+        assert_eq!(
+            known[0].services,
+            (ServiceFlags::NETWORK | ServiceFlags::WITNESS).to_u64()
+        );
+        assert_eq!(known[0].last_seen, base + 200);
+    }
+
+    #[test]
+    fn get_orders_by_last_seen_descending() {
+        let base = now() - 1000;
+        let mut manager = AddressManager::new();
+        manager.add(addr(1), 8333, ServiceFlags::NONE, base + 100);
+        manager.add(addr(2), 8333, ServiceFlags::NONE, base + 300);
+        manager.add(addr(3), 8333, ServiceFlags::NONE, base + 200);
+
+        let known = manager.get(3, None);
+        let seen: Vec<u64> = known.iter().map(|k| k.last_seen).collect();
+        assert_eq!(seen, vec![base + 300, base + 200, base + 100]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn run_self_advertisement_emits_on_every_tick_until_shutdown() {
+        let mut external = ExternalAddresses::new();
+        external.add(addr(1), 8333);
+
+        let emitted = Arc::new(Mutex::new(0u32));
+        let emitted_in_task = emitted.clone();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+
+        let task = tokio::spawn(async move {
+            run_self_advertisement(
+                &external,
+                Duration::from_secs(60),
+                |addrs| {
+                    assert_eq!(addrs.len(), 1);
+                    *emitted_in_task.lock().unwrap() += 1;
+                },
+                shutdown_rx,
+            )
+            .await;
+        });
+
+        tokio::time::advance(Duration::from_secs(60)).await;
+        tokio::time::advance(Duration::from_secs(60)).await;
+        tokio::task::yield_now().await;
+
+        shutdown_tx.send(()).unwrap();
+        task.await.unwrap();
+
+        assert_eq!(*emitted.lock().unwrap(), 2);
+    }
+}
+
+/// The set of addresses this node advertises for itself in `addr`/`addrv2` gossip (clearnet,
+/// Tor or I2P), so peers don't have to guess how to reach us back. Populated from the
+/// `externalip`/`externaladdr` config option and updatable at runtime via `setexternaladdr`.
+#[derive(Debug, Default, Clone)]
+pub struct ExternalAddresses {
+    addresses: Vec<(PeerAddress, u16)>,
+}
+
+impl ExternalAddresses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `address:port` to the set of externally reachable addresses we advertise, if
+    /// it isn't already present.
+    pub fn add(&mut self, address: PeerAddress, port: u16) {
+        if !self
+            .addresses
+            .iter()
+            .any(|(existing, existing_port)| *existing == address && *existing_port == port)
+        {
+            self.addresses.push((address, port));
+        }
+    }
+
+    /// The addresses we currently advertise about ourselves.
+    pub fn get(&self) -> &[(PeerAddress, u16)] {
+        &self.addresses
+    }
+}
+
+/// Calls `emit` with our current external addresses every `period`, until `shutdown` resolves.
+/// This is the node's periodic self-advertisement: telling connected peers how to reach us
+/// back every so often, rather than only at connection time, so they don't forget about us.
+pub async fn run_self_advertisement<F>(
+    external: &ExternalAddresses,
+    period: std::time::Duration,
+    mut emit: F,
+    shutdown: tokio::sync::oneshot::Receiver<()>,
+) where
+    F: FnMut(&[(PeerAddress, u16)]),
+{
+    let mut interval = tokio::time::interval(period);
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => emit(external.get()),
+            _ = &mut shutdown => return,
+        }
+    }
+}
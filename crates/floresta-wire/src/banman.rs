@@ -0,0 +1,257 @@
+//! Tracks misbehaving peers so we stop dialing or accepting connections from them for a
+//! while, persisting the ban list to disk (as `banlist.json` in the datadir) so it survives
+//! restarts.
+//!
+//! Bans are keyed by [`Subnet`] rather than a single address, so a whole CIDR range can be
+//! banned at once. The ban manager is consulted before we accept an inbound connection or
+//! dial a peer, and [`BanMan::discourage`] is the entry point the sync driver should call
+//! when a peer serves something invalid, e.g. a bad block (as in `test_sync_invalid_block`)
+//! or a Utreexo proof that doesn't match the accumulator.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::net::TcpStream;
+
+use crate::node_interface::PeerAddress;
+use crate::p2p_wire::connector::Connector;
+
+/// How long a ban lasts when the caller doesn't specify a duration, in seconds (24h, same
+/// default as Bitcoin Core's `setban`).
+pub const DEFAULT_BANTIME: u64 = 24 * 60 * 60;
+
+/// An IPv4/IPv6 subnet expressed in CIDR notation (`base/prefix_len`), so a single ban can
+/// cover a whole range instead of just one address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Subnet {
+    pub base: IpAddr,
+    pub prefix_len: u8,
+}
+
+/// An error returned when a string isn't a valid `ip` or `ip/prefix_len` subnet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubnetParseError;
+
+impl core::fmt::Display for SubnetParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "not a valid subnet (expected an IP or IP/prefix_len)")
+    }
+}
+
+impl std::error::Error for SubnetParseError {}
+
+impl Subnet {
+    /// A subnet containing exactly one address.
+    pub fn single(addr: IpAddr) -> Self {
+        let prefix_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        Self { base: addr, prefix_len }
+    }
+
+    /// Whether `addr` falls inside this subnet.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.base, addr) {
+            (IpAddr::V4(base), IpAddr::V4(addr)) => {
+                let mask = v4_mask(self.prefix_len);
+                u32::from(base) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(base), IpAddr::V6(addr)) => {
+                let mask = v6_mask(self.prefix_len);
+                u128::from(base) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Subnet {
+    type Err = SubnetParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((ip, prefix_len)) => {
+                let base = ip.parse().map_err(|_| SubnetParseError)?;
+                let prefix_len = prefix_len.parse().map_err(|_| SubnetParseError)?;
+                Ok(Self { base, prefix_len })
+            }
+            None => Ok(Self::single(s.parse().map_err(|_| SubnetParseError)?)),
+        }
+    }
+}
+
+fn v4_mask(prefix_len: u8) -> u32 {
+    let prefix_len = prefix_len.min(32);
+    if prefix_len == 0 {
+        0
+    } else {
+        u32::MAX << (32 - prefix_len as u32)
+    }
+}
+
+fn v6_mask(prefix_len: u8) -> u128 {
+    let prefix_len = prefix_len.min(128);
+    if prefix_len == 0 {
+        0
+    } else {
+        u128::MAX << (128 - prefix_len as u32)
+    }
+}
+
+/// A single ban record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanEntry {
+    /// The banned subnet.
+    pub subnet: Subnet,
+    /// Unix timestamp (seconds) after which this ban no longer applies.
+    pub ban_until: u64,
+    /// A short, human-readable reason for the ban (e.g. "invalid block").
+    pub reason: String,
+}
+
+/// Stores and persists the node's ban list.
+pub struct BanMan {
+    entries: HashMap<Subnet, BanEntry>,
+    path: PathBuf,
+}
+
+impl BanMan {
+    /// Loads the ban list from `<datadir>/banlist.json`, starting empty if it doesn't exist
+    /// yet.
+    pub fn load(datadir: &Path) -> io::Result<Self> {
+        let path = datadir.join("banlist.json");
+
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<Vec<BanEntry>>(&contents)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|entry| (entry.subnet, entry))
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self { entries, path })
+    }
+
+    /// Persists the current ban list to disk.
+    fn save(&self) -> io::Result<()> {
+        let entries: Vec<&BanEntry> = self.entries.values().collect();
+        let contents = serde_json::to_string(&entries).unwrap_or_default();
+        fs::write(&self.path, contents)
+    }
+
+    /// Bans `subnet` for `bantime_secs` (defaulting to [`DEFAULT_BANTIME`]), or until the
+    /// absolute unix timestamp `bantime_secs` when `absolute` is set.
+    pub fn ban(
+        &mut self,
+        subnet: Subnet,
+        bantime_secs: Option<u64>,
+        absolute: bool,
+        reason: String,
+    ) -> io::Result<()> {
+        let ban_until = if absolute {
+            bantime_secs.unwrap_or(0)
+        } else {
+            now() + bantime_secs.unwrap_or(DEFAULT_BANTIME)
+        };
+
+        self.entries.insert(
+            subnet,
+            BanEntry {
+                subnet,
+                ban_until,
+                reason,
+            },
+        );
+        self.save()
+    }
+
+    /// Bans the single address `addr` for [`DEFAULT_BANTIME`], recording `reason`, meant to be
+    /// called from the sync driver when a peer misbehaves (e.g. serves an invalid block or a
+    /// bogus Utreexo proof).
+    pub fn discourage(&mut self, addr: IpAddr, reason: &str) -> io::Result<()> {
+        self.ban(Subnet::single(addr), None, false, reason.to_string())
+    }
+
+    /// Removes the ban on exactly `subnet`, returning whether one existed. Bans on other
+    /// subnets that contain `subnet` are left in place.
+    pub fn unban(&mut self, subnet: &Subnet) -> io::Result<bool> {
+        let removed = self.entries.remove(subnet).is_some();
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Removes every ban.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.entries.clear();
+        self.save()
+    }
+
+    /// Lists all bans that haven't expired yet.
+    pub fn list(&self) -> Vec<BanEntry> {
+        let now = now();
+        self.entries
+            .values()
+            .filter(|entry| entry.ban_until > now)
+            .cloned()
+            .collect()
+    }
+
+    /// Whether `addr` is currently covered by a non-expired ban.
+    pub fn is_banned(&self, addr: IpAddr) -> bool {
+        let now = now();
+        self.entries
+            .values()
+            .any(|entry| entry.ban_until > now && entry.subnet.contains(addr))
+    }
+}
+
+/// Dials `address:port` through `connector`, refusing outright if the peer's IP is currently
+/// banned in `ban_man`. This is where [`BanMan`] actually gets consulted before we connect out,
+/// rather than just being populated and never read.
+///
+/// Addresses without a routable IP ([`PeerAddress::TorV3`], [`PeerAddress::I2p`]) bypass the
+/// ban check, since bans are tracked by IP/subnet and these addresses don't have one.
+pub async fn dial_unbanned<C: Connector>(
+    connector: &C,
+    ban_man: &BanMan,
+    address: &PeerAddress,
+    port: u16,
+) -> io::Result<TcpStream> {
+    let ip = match *address {
+        PeerAddress::Ipv4(ip) => Some(IpAddr::V4(ip)),
+        PeerAddress::Ipv6(ip) | PeerAddress::Cjdns(ip) => Some(IpAddr::V6(ip)),
+        PeerAddress::TorV3(_) | PeerAddress::I2p(_) => None,
+    };
+
+    if let Some(ip) = ip {
+        if ban_man.is_banned(ip) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{ip} is banned"),
+            ));
+        }
+    }
+
+    connector.connect(address, port).await
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
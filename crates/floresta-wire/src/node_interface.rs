@@ -0,0 +1,511 @@
+//! Types used to talk about peers at the node/RPC boundary, independent of the transport
+//! that actually carries the connection.
+//!
+//! [`PeerAddress`] mirrors `bitcoin::p2p::address::AddrV2`: besides regular IPv4/IPv6
+//! clearnet addresses, it also covers Tor v3, I2P and cjdns peers (BIP155), so a peer that
+//! was only ever reachable through one of those networks can still be added, displayed and
+//! reported on like any other.
+
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::net::SocketAddr;
+
+use bitcoin::Network;
+use serde::Serialize;
+
+/// An address we can dial or advertise for a peer, mirroring the network types defined by
+/// BIP155 (`AddrV2`).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PeerAddress {
+    /// A regular IPv4 clearnet address.
+    Ipv4(Ipv4Addr),
+    /// A regular IPv6 clearnet address.
+    Ipv6(Ipv6Addr),
+    /// A Tor v3 (Ed25519) onion service, carrying its 32-byte public key.
+    TorV3([u8; 32]),
+    /// An I2P destination, carrying its 32-byte `b32.i2p` hash.
+    I2p([u8; 32]),
+    /// A cjdns peer, living in the `fc00::/8` IPv6 range.
+    Cjdns(Ipv6Addr),
+}
+
+/// An error returned when a string doesn't parse into a known [`PeerAddress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddrParseError;
+
+impl core::fmt::Display for AddrParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "not a valid clearnet, Tor v3, I2P or cjdns address")
+    }
+}
+
+impl std::error::Error for AddrParseError {}
+
+impl PeerAddress {
+    /// Parses a host (optionally `host:port`) into a [`PeerAddress`] and the port we should
+    /// use to dial it. If no port is given, the conventional P2P port for `network` is used,
+    /// which is also the convention Tor and I2P peers are assumed to follow.
+    pub fn parse(input: &str, network: Network) -> Result<(Self, u16), AddrParseError> {
+        if let Ok(socket_addr) = input.parse::<SocketAddr>() {
+            return Ok((Self::from_ip(socket_addr.ip()), socket_addr.port()));
+        }
+
+        if let Ok(ip) = input.parse::<IpAddr>() {
+            return Ok((Self::from_ip(ip), Self::default_port(network)));
+        }
+
+        let (host, port) = split_host_port(input, network);
+
+        if let Some(onion) = host.strip_suffix(".onion") {
+            return Ok((Self::parse_onion(onion)?, port));
+        }
+
+        if let Some(i2p) = host.strip_suffix(".b32.i2p") {
+            return Ok((Self::parse_i2p(i2p)?, port));
+        }
+
+        Err(AddrParseError)
+    }
+
+    fn from_ip(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(v4) => PeerAddress::Ipv4(v4),
+            // cjdns lives in fc00::/8, i.e. the first octet is 0xfc.
+            IpAddr::V6(v6) if v6.octets()[0] == 0xfc => PeerAddress::Cjdns(v6),
+            IpAddr::V6(v6) => PeerAddress::Ipv6(v6),
+        }
+    }
+
+    /// A Tor v3 address is the base32 encoding of `pubkey(32) || checksum(2) || version(1)`,
+    /// 56 characters long. We re-derive the checksum to reject addresses that aren't
+    /// actually valid, rather than silently truncating them to their pubkey.
+    fn parse_onion(host: &str) -> Result<Self, AddrParseError> {
+        if host.len() != 56 {
+            return Err(AddrParseError);
+        }
+
+        let decoded = base32_decode(host).ok_or(AddrParseError)?;
+        if decoded.len() != 35 {
+            return Err(AddrParseError);
+        }
+
+        let mut pubkey = [0u8; 32];
+        pubkey.copy_from_slice(&decoded[..32]);
+        let checksum = [decoded[32], decoded[33]];
+        let version = decoded[34];
+
+        if version != ONION_VERSION || checksum != onion_checksum(&pubkey) {
+            return Err(AddrParseError);
+        }
+
+        Ok(PeerAddress::TorV3(pubkey))
+    }
+
+    /// An I2P `b32.i2p` address is the base32 encoding of a 32-byte destination hash, 52
+    /// characters long (with the final padding bits dropped).
+    fn parse_i2p(host: &str) -> Result<Self, AddrParseError> {
+        if host.len() != 52 {
+            return Err(AddrParseError);
+        }
+
+        let decoded = base32_decode(host).ok_or(AddrParseError)?;
+        if decoded.len() < 32 {
+            return Err(AddrParseError);
+        }
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&decoded[..32]);
+        Ok(PeerAddress::I2p(hash))
+    }
+
+    /// The network this address belongs to, as reported by `getpeerinfo`.
+    pub fn network(&self) -> &'static str {
+        match self {
+            PeerAddress::Ipv4(_) => "ipv4",
+            PeerAddress::Ipv6(_) => "ipv6",
+            PeerAddress::TorV3(_) => "torv3",
+            PeerAddress::I2p(_) => "i2p",
+            PeerAddress::Cjdns(_) => "cjdns",
+        }
+    }
+
+    /// Returns the conventional P2P port for `network`, used when an address is given
+    /// without an explicit port.
+    ///
+    /// TODO: use `NetworkExt` to get this once
+    /// https://github.com/rust-bitcoin/rust-bitcoin/pull/4639 makes it into a release.
+    pub fn default_port(network: Network) -> u16 {
+        match network {
+            Network::Bitcoin => 8333,
+            Network::Signet => 38333,
+            Network::Testnet => 18333,
+            Network::Testnet4 => 48333,
+            Network::Regtest => 18444,
+        }
+    }
+}
+
+impl core::fmt::Display for PeerAddress {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PeerAddress::Ipv4(ip) => write!(f, "{ip}"),
+            PeerAddress::Ipv6(ip) => write!(f, "{ip}"),
+            PeerAddress::Cjdns(ip) => write!(f, "{ip}"),
+            PeerAddress::TorV3(pubkey) => {
+                let checksum = onion_checksum(pubkey);
+                let mut bytes = Vec::with_capacity(35);
+                bytes.extend_from_slice(pubkey);
+                bytes.extend_from_slice(&checksum);
+                bytes.push(ONION_VERSION);
+                write!(f, "{}.onion", base32_encode(&bytes))
+            }
+            PeerAddress::I2p(hash) => write!(f, "{}.b32.i2p", base32_encode(hash)),
+        }
+    }
+}
+
+impl Serialize for PeerAddress {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// Splits `host:port` into its parts, falling back to `network`'s default port when there's
+/// no `:port` suffix (or what follows the last `:` isn't a port number, as happens with bare
+/// IPv6 addresses, which are handled earlier by [`PeerAddress::parse`]).
+fn split_host_port(input: &str, network: Network) -> (&str, u16) {
+    match input.rsplit_once(':') {
+        Some((host, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+            match port.parse() {
+                Ok(port) => (host, port),
+                Err(_) => (input, PeerAddress::default_port(network)),
+            }
+        }
+        _ => (input, PeerAddress::default_port(network)),
+    }
+}
+
+/// The only onion service version in use today (Tor v2 is long retired).
+const ONION_VERSION: u8 = 0x03;
+
+/// Derives the 2-byte checksum embedded in a Tor v3 onion address: the first two bytes of
+/// `SHA3-256(".onion checksum" || pubkey || version)` (tor-spec.txt, section 6).
+fn onion_checksum(pubkey: &[u8; 32]) -> [u8; 2] {
+    let mut data = Vec::with_capacity(15 + 32 + 1);
+    data.extend_from_slice(b".onion checksum");
+    data.extend_from_slice(pubkey);
+    data.push(ONION_VERSION);
+
+    let digest = sha3_256(&data);
+    [digest[0], digest[1]]
+}
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Decodes an RFC4648 base32 string (the alphabet shared by Tor's and I2P's addressing
+/// schemes), case-insensitively and without requiring padding.
+fn base32_decode(input: &str) -> Option<Vec<u8>> {
+    let mut bits: u32 = 0;
+    let mut bit_count = 0u32;
+    let mut out = Vec::with_capacity(input.len() * 5 / 8);
+
+    for byte in input.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&b| b == byte.to_ascii_lowercase())? as u32;
+        bits = (bits << 5) | value;
+        bit_count += 5;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            out.push((bits >> bit_count) as u8);
+        }
+    }
+
+    Some(out)
+}
+
+/// Encodes bytes as an unpadded, lowercase RFC4648 base32 string.
+fn base32_encode(input: &[u8]) -> String {
+    let mut bits: u32 = 0;
+    let mut bit_count = 0u32;
+    let mut out = String::with_capacity(input.len().div_ceil(5) * 8);
+
+    for &byte in input {
+        bits = (bits << 8) | byte as u32;
+        bit_count += 8;
+        while bit_count >= 5 {
+            bit_count -= 5;
+            out.push(BASE32_ALPHABET[((bits >> bit_count) & 0x1f) as usize] as char);
+        }
+    }
+
+    if bit_count > 0 {
+        out.push(BASE32_ALPHABET[((bits << (5 - bit_count)) & 0x1f) as usize] as char);
+    }
+
+    out
+}
+
+/// A small, self-contained SHA3-256 (Keccak-f\[1600\]) implementation, used only to derive
+/// the checksum embedded in Tor v3 onion addresses. Pulling in a whole hashing crate for two
+/// bytes of output isn't worth it, and `bitcoin::hashes` doesn't expose SHA3.
+const KECCAK_ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+const KECCAK_RHO: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+const KECCAK_PI: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+fn keccak_f1600(state: &mut [u64; 25]) {
+    for &round_constant in &KECCAK_ROUND_CONSTANTS {
+        // Theta
+        let mut c = [0u64; 5];
+        for (x, slot) in c.iter_mut().enumerate() {
+            *slot = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        let mut d = [0u64; 5];
+        for x in 0..5 {
+            d[x] = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+        }
+        for x in 0..5 {
+            for y in 0..5 {
+                state[x + y * 5] ^= d[x];
+            }
+        }
+
+        // Rho and pi
+        let mut last = state[1];
+        for i in 0..24 {
+            let p = KECCAK_PI[i];
+            let tmp = state[p];
+            state[p] = last.rotate_left(KECCAK_RHO[i]);
+            last = tmp;
+        }
+
+        // Chi
+        for y in 0..5 {
+            let row: [u64; 5] = core::array::from_fn(|x| state[x + y * 5]);
+            for x in 0..5 {
+                state[x + y * 5] = row[x] ^ ((!row[(x + 1) % 5]) & row[(x + 2) % 5]);
+            }
+        }
+
+        // Iota
+        state[0] ^= round_constant;
+    }
+}
+
+/// Absorbs one full, already-padded `RATE`-byte block into `state`.
+fn keccak_absorb(state: &mut [u64; 25], block: &[u8]) {
+    for (i, chunk) in block.chunks(8).enumerate() {
+        let mut lane = [0u8; 8];
+        lane[..chunk.len()].copy_from_slice(chunk);
+        state[i] ^= u64::from_le_bytes(lane);
+    }
+    keccak_f1600(state);
+}
+
+/// Computes `SHA3-256(input)`.
+fn sha3_256(input: &[u8]) -> [u8; 32] {
+    // Rate, in bytes, for a 256-bit capacity sponge: (1600 - 2*256) / 8.
+    const RATE: usize = 136;
+
+    let mut state = [0u64; 25];
+    let mut chunks = input.chunks_exact(RATE);
+    for block in &mut chunks {
+        keccak_absorb(&mut state, block);
+    }
+
+    // Pad the final (possibly empty) partial block: SHA3's domain-separated `01` suffix
+    // followed by `10*1` padding, i.e. 0x06 at the message boundary and 0x80 at the end.
+    let mut last_block = [0u8; RATE];
+    let remainder = chunks.remainder();
+    last_block[..remainder.len()].copy_from_slice(remainder);
+    last_block[remainder.len()] ^= 0x06;
+    last_block[RATE - 1] ^= 0x80;
+    keccak_absorb(&mut state, &last_block);
+
+    let mut output = [0u8; 32];
+    for (i, word) in state.iter().take(4).enumerate() {
+        output[i * 8..i * 8 + 8].copy_from_slice(&word.to_le_bytes());
+    }
+    output
+}
+
+/// Information about a connected peer, as reported by `getpeerinfo`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerInfo {
+    /// A node-local identifier for this peer, used to reference it in other RPCs.
+    pub id: u32,
+    /// The address we're connected to this peer through.
+    pub address: PeerAddress,
+    /// The port we're connected to this peer through.
+    pub port: u16,
+    /// The network this peer's address belongs to (`"ipv4"`, `"ipv6"`, `"torv3"`, `"i2p"` or
+    /// `"cjdns"`), as reported by [`PeerAddress::network`].
+    pub network: String,
+}
+
+impl PeerInfo {
+    /// Builds a `PeerInfo`, deriving `network` from `address` so callers don't have to keep
+    /// the two in sync themselves.
+    pub fn new(id: u32, address: PeerAddress, port: u16) -> Self {
+        let network = address.network().to_string();
+        Self {
+            id,
+            address,
+            port,
+            network,
+        }
+    }
+}
+
+/// The peer-management operations the JSON-RPC layer needs from the running node: connecting
+/// to and disconnecting peers, banning, and the address book. `RpcImpl::node` is expected to
+/// implement this, so the RPC handlers in `json_rpc::network` stay grounded in a real contract
+/// instead of calling methods that exist nowhere in the crate.
+pub trait NodeInterface: Send + Sync {
+    /// The error type returned by these operations; displayed back to RPC callers.
+    type Error: std::fmt::Display;
+
+    /// Checks that the node's event loop is still responsive.
+    async fn ping(&self) -> Result<bool, Self::Error>;
+
+    /// Adds `address:port` as a peer to connect to, optionally negotiating the v2 (BIP324)
+    /// transport.
+    async fn add_peer(
+        &self,
+        address: PeerAddress,
+        port: u16,
+        v2transport: bool,
+    ) -> Result<(), Self::Error>;
+
+    /// Stops treating `address:port` as a peer to reconnect to.
+    async fn remove_peer(&self, address: PeerAddress, port: u16) -> Result<(), Self::Error>;
+
+    /// Attempts a single, one-off connection to `address:port` without adding it as a
+    /// persistent peer.
+    async fn onetry_peer(
+        &self,
+        address: PeerAddress,
+        port: u16,
+        v2transport: bool,
+    ) -> Result<(), Self::Error>;
+
+    /// Disconnects the peer at `address:port` if one is currently connected, returning whether
+    /// it was.
+    async fn disconnect_peer(&self, address: PeerAddress, port: u16) -> Result<bool, Self::Error>;
+
+    /// Returns information about every currently connected peer.
+    async fn get_peer_info(&self) -> Result<Vec<PeerInfo>, Self::Error>;
+
+    /// Bans `subnet`, as described on [`BanMan::ban`](crate::banman::BanMan::ban).
+    async fn ban(
+        &self,
+        subnet: crate::banman::Subnet,
+        bantime: Option<u64>,
+        absolute: bool,
+    ) -> Result<(), Self::Error>;
+
+    /// Removes a ban on `subnet`, returning whether one existed.
+    async fn unban(&self, subnet: crate::banman::Subnet) -> Result<bool, Self::Error>;
+
+    /// Lists all bans that haven't expired yet.
+    async fn list_banned(&self) -> Result<Vec<crate::banman::BanEntry>, Self::Error>;
+
+    /// Removes every ban.
+    async fn clear_banned(&self) -> Result<(), Self::Error>;
+
+    /// Returns up to `count` known-good peer addresses, optionally restricted to `network`
+    /// (see [`AddressManager::get`](crate::address_manager::AddressManager::get)).
+    async fn get_node_addresses(
+        &self,
+        count: usize,
+        network: Option<&str>,
+    ) -> Result<Vec<crate::address_manager::KnownAddress>, Self::Error>;
+
+    /// Adds `address:port` to the set of externally reachable addresses this node advertises.
+    async fn add_external_addr(&self, address: PeerAddress, port: u16) -> Result<(), Self::Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes a hex string into a 32-byte array, for use in test vectors.
+    fn hex_to_bytes32(hex: &str) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn sha3_256_matches_a_known_test_vector() {
+        // SHA3-256("") from NIST's test vectors.
+        assert_eq!(
+            sha3_256(b""),
+            hex_to_bytes32("a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a")
+        );
+    }
+
+    #[test]
+    fn tor_v3_address_round_trips_through_display_and_parse() {
+        let address = PeerAddress::TorV3([0x42; 32]);
+
+        let rendered = address.to_string();
+        assert_eq!(rendered.len(), 56 + ".onion".len());
+        assert_eq!(rendered, rendered.to_lowercase(), "onion addresses must render lowercase");
+
+        let (parsed, port) = PeerAddress::parse(&rendered, Network::Bitcoin).unwrap();
+        assert_eq!(parsed, address);
+        assert_eq!(port, PeerAddress::default_port(Network::Bitcoin));
+    }
+
+    #[test]
+    fn tor_v3_address_with_tampered_checksum_is_rejected() {
+        let rendered = PeerAddress::TorV3([0x42; 32]).to_string();
+        let onion = rendered.strip_suffix(".onion").unwrap();
+
+        // Flip the first base32 character, corrupting the pubkey without touching the
+        // (now mismatched) checksum.
+        let mut chars: Vec<char> = onion.chars().collect();
+        chars[0] = if chars[0] == 'a' { 'b' } else { 'a' };
+        let tampered = format!("{}.onion", chars.into_iter().collect::<String>());
+
+        assert!(PeerAddress::parse(&tampered, Network::Bitcoin).is_err());
+    }
+}
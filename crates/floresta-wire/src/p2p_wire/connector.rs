@@ -0,0 +1,210 @@
+//! Outbound connection establishment for the P2P layer.
+//!
+//! By default peers are dialed directly over TCP ([`ClearnetConnector`]). When a SOCKS5
+//! proxy is configured (e.g. a local Tor daemon), [`Socks5Connector`] routes the connection
+//! through it instead, handing the destination hostname to the proxy rather than resolving
+//! it locally. This is required to reach `.onion`/`.b32.i2p` peers, and mirrors how Bitcoin
+//! Core separates `netbase` proxy handling from raw TCP. [`connector_for`] is where that
+//! choice is made, from the node's configured proxy.
+
+use std::future::Future;
+use std::io;
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+use crate::node_interface::PeerAddress;
+
+/// Establishes an outbound connection to a peer, abstracting over whether we dial directly
+/// or through a proxy.
+///
+/// `connect` returns a boxed future rather than `impl Future` so this trait stays
+/// dyn-compatible: [`connector_for`] picks the concrete connector once, at startup, from the
+/// configured proxy, and hands callers a `Box<dyn Connector>` so the rest of the node doesn't
+/// need to be generic over which one is in use.
+pub trait Connector: Send + Sync {
+    /// Connects to `address:port`, returning the resulting TCP stream.
+    fn connect<'a>(
+        &'a self,
+        address: &'a PeerAddress,
+        port: u16,
+    ) -> Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send + 'a>>;
+}
+
+/// Picks the [`Connector`] to use based on the configured proxy: [`Socks5Connector`] when one
+/// is set (so `.onion`/`.b32.i2p` peers and clearnet peers alike are dialed through it), or
+/// [`ClearnetConnector`] otherwise. This is the one place the choice is made; everything else
+/// just dials through the returned `Box<dyn Connector>`.
+pub fn connector_for(proxy: Option<SocketAddr>) -> Box<dyn Connector> {
+    match proxy {
+        Some(proxy) => Box::new(Socks5Connector::new(proxy)),
+        None => Box::new(ClearnetConnector),
+    }
+}
+
+/// Dials peers directly over TCP. Only clearnet addresses ([`PeerAddress::Ipv4`],
+/// [`PeerAddress::Ipv6`] and [`PeerAddress::Cjdns`]) can be reached this way.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClearnetConnector;
+
+impl Connector for ClearnetConnector {
+    fn connect<'a>(
+        &'a self,
+        address: &'a PeerAddress,
+        port: u16,
+    ) -> Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send + 'a>> {
+        Box::pin(async move {
+            let ip = match *address {
+                PeerAddress::Ipv4(ip) => IpAddr::V4(ip),
+                PeerAddress::Ipv6(ip) | PeerAddress::Cjdns(ip) => IpAddr::V6(ip),
+                PeerAddress::TorV3(_) | PeerAddress::I2p(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "a proxy is required to reach Tor/I2P peers; set `proxy` in the config",
+                    ))
+                }
+            };
+
+            TcpStream::connect(SocketAddr::new(ip, port)).await
+        })
+    }
+}
+
+/// Dials peers through a SOCKS5 proxy (e.g. a local Tor daemon on `127.0.0.1:9050`),
+/// resolving the destination hostname on the proxy side rather than doing local DNS.
+#[derive(Debug, Clone, Copy)]
+pub struct Socks5Connector {
+    /// Address of the SOCKS5 proxy to dial through.
+    proxy: SocketAddr,
+}
+
+impl Socks5Connector {
+    /// Creates a connector that routes every outbound connection through `proxy`.
+    pub fn new(proxy: SocketAddr) -> Self {
+        Self { proxy }
+    }
+}
+
+impl Connector for Socks5Connector {
+    fn connect<'a>(
+        &'a self,
+        address: &'a PeerAddress,
+        port: u16,
+    ) -> Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut stream = TcpStream::connect(self.proxy).await?;
+            socks5_connect(&mut stream, &address.to_string(), port).await?;
+            Ok(stream)
+        })
+    }
+}
+
+/// Performs a no-auth SOCKS5 `CONNECT` handshake to `host:port` over `stream`, using the
+/// domain-name address type so resolution happens on the proxy side (RFC 1928).
+async fn socks5_connect(stream: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+    // Greeting: version 5, one method on offer, no authentication required.
+    stream.write_all(&[0x05, 0x01, 0x00]).await?;
+    let mut method_reply = [0u8; 2];
+    stream.read_exact(&mut method_reply).await?;
+    if method_reply != [0x05, 0x00] {
+        return Err(io::Error::other(
+            "SOCKS5 proxy rejected the no-auth handshake",
+        ));
+    }
+
+    // CONNECT request using the domain-name address type (0x03).
+    let host = host.as_bytes();
+    let mut request = vec![0x05, 0x01, 0x00, 0x03, host.len() as u8];
+    request.extend_from_slice(host);
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply_header = [0u8; 4];
+    stream.read_exact(&mut reply_header).await?;
+    if reply_header[1] != 0x00 {
+        return Err(io::Error::other(format!(
+            "SOCKS5 proxy returned error code {}",
+            reply_header[1]
+        )));
+    }
+
+    // Consume and discard the bound address the proxy echoes back.
+    let remainder = match reply_header[3] {
+        0x01 => 4 + 2,
+        0x04 => 16 + 2,
+        0x03 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await?;
+            len[0] as usize + 2
+        }
+        _ => return Err(io::Error::other("SOCKS5 proxy returned an unknown address type")),
+    };
+    let mut discard = vec![0u8; remainder];
+    stream.read_exact(&mut discard).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn connector_for_without_a_proxy_dials_clearnet_directly() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let connector = connector_for(None);
+        let address = PeerAddress::Ipv4(Ipv4Addr::new(127, 0, 0, 1));
+
+        let (accepted, connected) =
+            tokio::join!(listener.accept(), connector.connect(&address, port));
+        assert!(accepted.is_ok());
+        assert!(connected.is_ok());
+    }
+
+    #[tokio::test]
+    async fn connector_for_with_a_proxy_routes_through_socks5() {
+        let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_addr = proxy.local_addr().unwrap();
+
+        let proxy_task = tokio::spawn(async move {
+            let (mut stream, _) = proxy.accept().await.unwrap();
+
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [0x05, 0x01, 0x00]);
+            stream.write_all(&[0x05, 0x00]).await.unwrap();
+
+            let mut request_header = [0u8; 5];
+            stream.read_exact(&mut request_header).await.unwrap();
+            assert_eq!(&request_header[..4], [0x05, 0x01, 0x00, 0x03]);
+            let host_len = request_header[4] as usize;
+            let mut host = vec![0u8; host_len + 2];
+            stream.read_exact(&mut host).await.unwrap();
+
+            // Reply with success and a dummy bound address (IPv4, 0.0.0.0:0).
+            stream
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+
+            String::from_utf8(host[..host_len].to_vec()).unwrap()
+        });
+
+        let connector = connector_for(Some(proxy_addr));
+        let address = PeerAddress::TorV3([0x42; 32]);
+        connector.connect(&address, 1234).await.unwrap();
+
+        let dialed_host = proxy_task.await.unwrap();
+        assert_eq!(dialed_host, address.to_string());
+    }
+}
@@ -0,0 +1,92 @@
+//! Bridges block/proof validation failures to [`BanMan`], so a peer that serves something
+//! invalid actually gets discouraged instead of just having its data rejected.
+//!
+//! The sync driver calls [`handle_validation_result`] right after validating a block or a
+//! Utreexo proof received from a peer (see `test_sync_invalid_block`), passing the `Result`
+//! it got back and one of the reason constants below. On `Err`, the peer is discouraged; on
+//! `Ok`, this is a no-op.
+
+use std::fmt::Display;
+use std::net::IpAddr;
+
+use crate::banman::BanMan;
+
+/// Reason recorded when a peer sends a block that fails consensus validation.
+pub const INVALID_BLOCK_REASON: &str = "invalid block";
+
+/// Reason recorded when a peer sends a Utreexo proof that doesn't match our accumulator.
+pub const INVALID_UTREEXO_PROOF_REASON: &str = "invalid utreexo proof";
+
+/// Discourages `peer_addr` in `ban_man` for [`DEFAULT_BANTIME`](crate::banman::DEFAULT_BANTIME),
+/// recording `reason` on the ban. Errors persisting the ban list are logged rather than
+/// propagated, since a failure to write `banlist.json` shouldn't stop us from dropping the
+/// connection.
+pub fn discourage_peer(ban_man: &mut BanMan, peer_addr: IpAddr, reason: &str) {
+    if let Err(e) = ban_man.discourage(peer_addr, reason) {
+        log::warn!("failed to persist ban for {peer_addr} ({reason}): {e}");
+    }
+}
+
+/// Discourages `peer_addr` if `result` is an `Err`, otherwise does nothing. This is the single
+/// call site the sync driver is expected to use after validating something a peer sent us, so
+/// that every validation failure (not just some) results in the peer being discouraged.
+pub fn handle_validation_result<E: Display>(
+    ban_man: &mut BanMan,
+    peer_addr: IpAddr,
+    result: &Result<(), E>,
+    reason: &str,
+) {
+    if let Err(e) = result {
+        log::warn!("peer {peer_addr} failed validation ({reason}): {e}");
+        discourage_peer(ban_man, peer_addr, reason);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+    use std::net::Ipv4Addr;
+
+    use floresta_chain::pruned_utreexo::consensus::Consensus;
+    use floresta_common::prelude::HashMap;
+
+    use super::discourage_peer;
+    use super::handle_validation_result;
+    use super::INVALID_BLOCK_REASON;
+    use crate::banman::BanMan;
+
+    fn temp_ban_man(suffix: &str) -> BanMan {
+        let datadir = format!("./tmp-db/{}.{suffix}", rand::random::<u32>());
+        std::fs::create_dir_all(&datadir).unwrap();
+        BanMan::load(std::path::Path::new(&datadir)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn discourage_peer_bans_the_given_address() {
+        let mut ban_man = temp_ban_man("validation");
+        let peer_addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+
+        assert!(!ban_man.is_banned(peer_addr));
+
+        discourage_peer(&mut ban_man, peer_addr, INVALID_BLOCK_REASON);
+
+        assert!(ban_man.is_banned(peer_addr));
+        assert_eq!(ban_man.list()[0].reason, INVALID_BLOCK_REASON);
+    }
+
+    /// Exercises the actual call site: a peer that sends a block failing consensus validation
+    /// (here, a block with no transactions at all, which `Consensus::verify_block_transactions`
+    /// rejects as [`BlockValidationErrors::EmptyBlock`]) gets discouraged, not just dropped.
+    #[tokio::test]
+    async fn peer_serving_a_consensus_invalid_block_gets_discouraged() {
+        let mut ban_man = temp_ban_man("validation");
+        let peer_addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+
+        let result = Consensus::verify_block_transactions(0, HashMap::new(), &[], 0, false, 0);
+        assert!(result.is_err());
+
+        handle_validation_result(&mut ban_man, peer_addr, &result, INVALID_BLOCK_REASON);
+
+        assert!(ban_man.is_banned(peer_addr));
+    }
+}